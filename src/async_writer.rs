@@ -0,0 +1,263 @@
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+use chrono::{DateTime, Local};
+use flexi_logger::writers::LogWriter;
+use flexi_logger::{DeferredNow, FormatFunction};
+use log::{kv, Level, Record};
+
+use crate::REPLAYED_TIMESTAMP_KEY;
+
+/// What [`AsyncWriter`] does with a record when its channel is full.
+pub enum Overflow {
+    /// Block the logging thread until there is room on the channel.
+    Block,
+    /// Drop the record instead of blocking the logging thread.
+    Drop,
+}
+
+/// A message sent from [`AsyncWriter::write`] to its background thread.
+enum Message {
+    Record(OwnedRecord),
+    Flush(SyncSender<std::io::Result<()>>),
+}
+
+/// An owned copy of a `log::kv::Value`, keeping its original kind instead of collapsing
+/// everything to a string. This matters for formatters like [`crate::go_log_json_format`], which
+/// render numbers and booleans unquoted but strings quoted.
+enum OwnedKvValue {
+    Str(String),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+}
+
+impl OwnedKvValue {
+    fn from_value(value: &kv::Value<'_>) -> Self {
+        if let Some(s) = value.to_borrowed_str() {
+            OwnedKvValue::Str(s.to_string())
+        } else if let Some(b) = value.to_bool() {
+            OwnedKvValue::Bool(b)
+        } else if let Some(n) = value.to_i64() {
+            OwnedKvValue::I64(n)
+        } else if let Some(n) = value.to_u64() {
+            OwnedKvValue::U64(n)
+        } else if let Some(n) = value.to_f64() {
+            OwnedKvValue::F64(n)
+        } else {
+            OwnedKvValue::Str(value.to_string())
+        }
+    }
+
+    fn as_kv_value(&self) -> kv::Value<'_> {
+        match self {
+            OwnedKvValue::Str(s) => kv::Value::from(s.as_str()),
+            OwnedKvValue::I64(n) => kv::Value::from(*n),
+            OwnedKvValue::U64(n) => kv::Value::from(*n),
+            OwnedKvValue::F64(n) => kv::Value::from(*n),
+            OwnedKvValue::Bool(b) => kv::Value::from(*b),
+        }
+    }
+}
+
+/// An owned copy of the borrowed [`Record`] fields, so a log call can hand its record off to the
+/// background thread instead of formatting it on the calling thread.
+struct OwnedRecord {
+    level: Level,
+    target: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+    key_values: Vec<(String, OwnedKvValue)>,
+    /// When the record was actually logged, captured from the `DeferredNow` the logging macro
+    /// passed to [`AsyncWriter::write`] — not when the background thread gets around to draining
+    /// it. See [`crate::REPLAYED_TIMESTAMP_KEY`] for how this crosses back into a `Record`.
+    logged_at: DateTime<Local>,
+}
+
+impl OwnedRecord {
+    fn from_record(record: &Record, logged_at: DateTime<Local>) -> Self {
+        struct Collect<'a>(&'a mut Vec<(String, OwnedKvValue)>);
+
+        impl<'kvs> kv::VisitSource<'kvs> for Collect<'_> {
+            fn visit_pair(
+                &mut self,
+                key: kv::Key<'kvs>,
+                value: kv::Value<'kvs>,
+            ) -> Result<(), kv::Error> {
+                self.0
+                    .push((key.as_str().to_string(), OwnedKvValue::from_value(&value)));
+                Ok(())
+            }
+        }
+
+        let mut key_values = Vec::new();
+        let _ = record.key_values().visit(&mut Collect(&mut key_values));
+
+        Self {
+            level: record.level(),
+            target: record.target().to_string(),
+            module_path: record.module_path().map(str::to_string),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            message: record.args().to_string(),
+            key_values,
+            logged_at,
+        }
+    }
+}
+
+/// Replays the key-value pairs collected by [`OwnedRecord::from_record`] as a [`kv::Source`], so
+/// the rebuilt [`Record`] on the background thread still carries them with their original kind.
+struct OwnedKvSource(Vec<(String, OwnedKvValue)>);
+
+impl kv::Source for OwnedKvSource {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn kv::VisitSource<'kvs>) -> Result<(), kv::Error> {
+        for (key, value) in &self.0 {
+            visitor.visit_pair(kv::Key::from(key.as_str()), value.as_kv_value())?;
+        }
+        Ok(())
+    }
+}
+
+fn writer_gone() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "the AsyncWriter background thread is gone",
+    )
+}
+
+/// Wraps any [`LogWriter`] and moves its formatting and I/O onto a dedicated background thread,
+/// so logging from a hot path never blocks on disk (or syslog, or anything else the wrapped
+/// writer does).
+///
+/// Each [`write`](LogWriter::write) call only copies the record's fields into an owned message,
+/// including the time it was logged, and pushes it onto a bounded channel; the background thread
+/// drains the channel and calls the wrapped writer's `write` for real. That keeps a backlogged
+/// queue from showing up as wrong, drain-time timestamps in the output - see
+/// [`crate::REPLAYED_TIMESTAMP_KEY`]. Dropping the `AsyncWriter` closes the channel and joins the
+/// background thread, so nothing already queued is lost.
+///
+/// Configure the wrapped writer's format *before* passing it to [`AsyncWriter::new`] -
+/// [`LogWriter::format`] on this writer is a no-op, since by the time it could be called the
+/// inner writer has already moved to the background thread.
+pub struct AsyncWriter {
+    sender: Option<SyncSender<Message>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+    overflow: Overflow,
+    max_log_level: log::LevelFilter,
+}
+
+impl AsyncWriter {
+    /// Spawns the background thread and starts forwarding to `inner`.
+    ///
+    /// `capacity` bounds how many unwritten records may queue up; once full, `overflow` decides
+    /// whether [`write`](LogWriter::write) blocks or silently drops the record.
+    pub fn new(inner: Box<dyn LogWriter>, capacity: usize, overflow: Overflow) -> Self {
+        let max_log_level = inner.max_log_level();
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let worker = thread::Builder::new()
+            .name("fil-logger-async-writer".to_string())
+            .spawn(move || {
+                let mut inner = inner;
+                for message in receiver {
+                    match message {
+                        Message::Record(owned) => {
+                            let mut key_values = owned.key_values;
+                            key_values.push((
+                                REPLAYED_TIMESTAMP_KEY.to_string(),
+                                OwnedKvValue::I64(owned.logged_at.timestamp_millis()),
+                            ));
+                            let kv_source = OwnedKvSource(key_values);
+                            // `format_args!` must be bound to a local: the `Arguments` it
+                            // produces borrows its own temporaries, which would otherwise be
+                            // dropped at the end of this statement while `record` still borrows
+                            // them.
+                            let args = format_args!("{}", owned.message);
+                            let record = Record::builder()
+                                .level(owned.level)
+                                .target(&owned.target)
+                                .module_path(owned.module_path.as_deref())
+                                .file(owned.file.as_deref())
+                                .line(owned.line)
+                                .args(args)
+                                .key_values(&kv_source)
+                                .build();
+                            // `DeferredNow` has no public constructor that accepts a
+                            // pre-computed time, so this one will report the drain time if a
+                            // format function calls `now.now()` directly. `go_log_json_format`,
+                            // `color_logger_format` and `nocolor_logger_format` instead read the
+                            // record's `REPLAYED_TIMESTAMP_KEY` field above and use `logged_at`.
+                            let mut now = DeferredNow::new();
+                            let _ = inner.write(&mut now, &record);
+                        }
+                        Message::Flush(ack) => {
+                            let _ = ack.send(inner.flush());
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn fil-logger background writer thread");
+
+        Self {
+            sender: Some(sender),
+            worker: Mutex::new(Some(worker)),
+            overflow,
+            max_log_level,
+        }
+    }
+}
+
+impl LogWriter for AsyncWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+        let sender = self.sender.as_ref().ok_or_else(writer_gone)?;
+        let owned = OwnedRecord::from_record(record, *now.now());
+        match self.overflow {
+            Overflow::Block => sender
+                .send(Message::Record(owned))
+                .map_err(|_| writer_gone()),
+            Overflow::Drop => match sender.try_send(Message::Record(owned)) {
+                Ok(()) | Err(mpsc::TrySendError::Full(_)) => Ok(()),
+                Err(mpsc::TrySendError::Disconnected(_)) => Err(writer_gone()),
+            },
+        }
+    }
+
+    fn flush(&self) -> std::io::Result<()> {
+        let sender = self.sender.as_ref().ok_or_else(writer_gone)?;
+        let (ack_tx, ack_rx) = mpsc::sync_channel(1);
+        sender
+            .send(Message::Flush(ack_tx))
+            .map_err(|_| writer_gone())?;
+        ack_rx.recv().map_err(|_| writer_gone())?
+    }
+
+    fn format(&mut self, _format: FormatFunction) {
+        // No-op: the wrapped writer already moved to the background thread. Set the format on
+        // it before wrapping it in `AsyncWriter::new`.
+    }
+
+    fn max_log_level(&self) -> log::LevelFilter {
+        self.max_log_level
+    }
+
+    fn shutdown(&self) {
+        // Called by `LoggerHandle::shutdown`: wait until everything already queued is written.
+        let _ = self.flush();
+    }
+}
+
+impl Drop for AsyncWriter {
+    fn drop(&mut self) {
+        // Dropping the sender closes the channel, so the background thread's `for message in
+        // receiver` loop ends once it has drained everything already queued, instead of blocking
+        // forever.
+        self.sender.take();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
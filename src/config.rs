@@ -0,0 +1,119 @@
+use std::env;
+use std::fs::File;
+use std::path::PathBuf;
+
+use flexi_logger::writers::LogWriter;
+use flexi_logger::FormatFunction;
+
+use crate::{
+    color_logger_format, go_log_json_format, nocolor_logger_format, Cleanup, Facility, Rotate,
+    Transport,
+};
+
+/// The message format to use, independent of where the log ends up.
+pub enum LogFmt {
+    /// Human readable text, colored when stderr is a tty.
+    Text,
+    /// The JSON format used by [IPFS go-log].
+    ///
+    /// [IPFS go-log]: https://github.com/ipfs/go-log
+    Json,
+    /// A caller-supplied [`FormatFunction`].
+    Custom(FormatFunction),
+}
+
+impl Default for LogFmt {
+    /// Mirrors the historical behavior of reading the `GOLOG_LOG_FMT` environment variable, so
+    /// that callers who don't set [`LogConfig::format`] keep seeing the same output as before.
+    fn default() -> Self {
+        match env::var("GOLOG_LOG_FMT") {
+            Ok(ref format) if format == "json" => LogFmt::Json,
+            _ => LogFmt::Text,
+        }
+    }
+}
+
+impl LogFmt {
+    /// Resolves this format to the concrete [`FormatFunction`] flexi_logger should call.
+    pub(crate) fn resolve(&self) -> FormatFunction {
+        match self {
+            LogFmt::Json => go_log_json_format,
+            LogFmt::Text => {
+                if atty::is(atty::Stream::Stderr) {
+                    color_logger_format
+                } else {
+                    nocolor_logger_format
+                }
+            }
+            LogFmt::Custom(format) => *format,
+        }
+    }
+}
+
+/// Where log records end up.
+#[derive(Default)]
+pub enum LogDestination {
+    /// Write to stderr (the default).
+    #[default]
+    Stderr,
+    /// Write to stdout.
+    Stdout,
+    /// Write to an already opened [`File`].
+    File(File),
+    /// Write to a size- or age-rotated file, with old rotations cleaned up automatically. See
+    /// [`crate::RotatingFileWriter`].
+    RotatingFile {
+        /// Directory the active file and its rotations live in.
+        dir: PathBuf,
+        /// File name of the active file, e.g. `"node.log"`.
+        basename: String,
+        /// When and how to roll the active file over.
+        rotate: Rotate,
+        /// What to do with files that have already been rotated out.
+        keep: Cleanup,
+    },
+    /// Write RFC 5424 frames to a syslog collector. See [`crate::SyslogWriter`].
+    Syslog {
+        /// The syslog facility to tag frames with.
+        facility: Facility,
+        /// Where to send frames.
+        transport: Transport,
+    },
+    /// Write to a custom [`LogWriter`].
+    Writer(Box<dyn LogWriter>),
+}
+
+/// Programmatic configuration for [`crate::init_with`].
+///
+/// Use [`LogConfig::default`] and override only the fields that matter, e.g.:
+///
+/// ```
+/// use fil_logger::{LogConfig, LogFmt};
+///
+/// let config = LogConfig {
+///     filter: Some("debug".into()),
+///     format: LogFmt::Json,
+///     ..LogConfig::default()
+/// };
+/// ```
+#[derive(Default)]
+pub struct LogConfig {
+    /// Overrides the `RUST_LOG` environment variable. `None` falls back to reading it.
+    pub filter: Option<String>,
+    /// The message format to use.
+    pub format: LogFmt,
+    /// Where the formatted records are written to.
+    pub target: LogDestination,
+    /// An additional formatter applied to [`LogDestination::Writer`] only, via
+    /// [`LogWriter::format`]. Lets a custom writer keep its own framing while still picking a
+    /// formatter through the config, instead of baking one into the writer itself.
+    pub pipe_formatter: Option<FormatFunction>,
+    /// Moves formatting and I/O for [`LogDestination::File`] and
+    /// [`LogDestination::RotatingFile`] onto a background thread via [`crate::AsyncWriter`], so
+    /// logging from a hot path never blocks on disk. Has no effect on other destinations.
+    ///
+    /// Call [`crate::init_with`] directly and keep its returned `LoggerHandle` when you set this,
+    /// so you can call `handle.shutdown()` during your own shutdown sequence and flush whatever
+    /// is still queued on the background thread before the process exits.
+    pub async_writes: bool,
+}
@@ -35,15 +35,62 @@
 //! {"level":"error","ts":"2019-11-11T21:06:45.401+01:00","logger":"simple","caller":"examples/simple.rs:40","msg":"error!"}"
 //!
 //! [env_logger]: https://crates.io/crates/env_logger
+mod async_writer;
+mod config;
+mod rotating_file_writer;
 mod single_file_writer;
+mod syslog_writer;
 
-use std::env;
 use std::fs::File;
+use std::thread;
+use std::time::Duration;
 
-use atty;
+use chrono::{DateTime, Local, TimeZone};
+use flexi_logger::writers::LogWriter;
 use flexi_logger::{self, style, DeferredNow, FormatFunction, LogTarget, Record};
+use log::kv;
 use log::Level;
+
+pub use async_writer::{AsyncWriter, Overflow};
+pub use config::{LogConfig, LogDestination, LogFmt};
+pub use flexi_logger::{FlexiLoggerError, LoggerHandle};
+pub use rotating_file_writer::{Cleanup, Criterion, Naming, Rotate, RotatingFileWriter};
 pub use single_file_writer::SingleFileWriter;
+pub use syslog_writer::{Facility, SyslogWriter, Transport};
+
+/// The channel capacity of the [`AsyncWriter`] that [`LogConfig::async_writes`] sets up. It
+/// can't be changed through [`LogConfig`].
+const DEFAULT_ASYNC_CHANNEL_CAPACITY: usize = 1024;
+
+/// The key [`AsyncWriter`] attaches to a replayed record carrying the time it was originally
+/// logged, in milliseconds since the epoch. flexi_logger's `DeferredNow` has no public
+/// constructor that accepts a pre-computed time, so there's no way to hand the background thread
+/// a `DeferredNow` that reflects when the record was actually logged rather than when it was
+/// finally drained from the queue; stashing the real time as an ordinary structured field and
+/// having the format functions below prefer it is the only way to carry it across that boundary.
+pub(crate) const REPLAYED_TIMESTAMP_KEY: &str = "__fil_logger_replayed_ts";
+
+/// The timestamp a format function should render for `record`: the time [`AsyncWriter`] captured
+/// when the record was originally logged, if it was replayed through one, or `now`'s own
+/// (lazily computed) timestamp otherwise.
+fn record_timestamp(now: &mut DeferredNow, record: &Record) -> DateTime<Local> {
+    struct FindReplayedTimestamp(Option<DateTime<Local>>);
+
+    impl<'kvs> kv::VisitSource<'kvs> for FindReplayedTimestamp {
+        fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+            if key.as_str() == REPLAYED_TIMESTAMP_KEY {
+                if let Some(millis) = value.to_i64() {
+                    self.0 = Local.timestamp_millis_opt(millis).single();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let mut finder = FindReplayedTimestamp(None);
+    let _ = record.key_values().visit(&mut finder);
+    finder.0.unwrap_or_else(|| *now.now())
+}
 
 /// Logs in the same JSON format as [IPFS go-log] does.
 ///
@@ -59,6 +106,9 @@ pub use single_file_writer::SingleFileWriter;
 /// }
 /// ```
 ///
+/// Structured fields passed to the logging macros, e.g. `info!(peer_id = "Qm...", bytes = 1024;
+/// "sent block")`, are carried through as additional top-level fields on the JSON object.
+///
 /// [IPFS go-log]: https://github.com/ipfs/go-log
 pub fn go_log_json_format(
     writer: &mut dyn std::io::Write,
@@ -74,14 +124,85 @@ pub fn go_log_json_format(
     };
     write!(
         writer,
-        r#"{{"level":"{}","ts":"{}","logger":"{}","caller":"{}:{}","msg":"{}"}}"#,
+        r#"{{"level":"{}","ts":"{}","logger":"{}","caller":"{}:{}","msg":"{}""#,
         level,
-        now.now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+        record_timestamp(now, record).format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
         record.module_path().unwrap_or("<unnamed>"),
         record.file().unwrap_or("<unnamed>"),
         record.line().unwrap_or(0),
-        &record.args()
-    )
+        escape_json_str(&record.args().to_string()),
+    )?;
+
+    let mut visitor = JsonFieldsVisitor {
+        writer,
+        result: Ok(()),
+    };
+    let _ = record.key_values().visit(&mut visitor);
+    visitor.result?;
+
+    write!(writer, "}}")
+}
+
+/// Escapes a string so it can be embedded in a JSON string literal: quotes, backslashes,
+/// newlines and other control characters all need to be escaped, or the emitted line isn't valid
+/// JSON.
+fn escape_json_str(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a single `log::kv` value as a JSON value: strings are quoted and escaped, the
+/// primitive types go_log understands are written unquoted, and anything else falls back to its
+/// `Display` representation quoted as a string.
+fn write_json_kv_value(
+    writer: &mut dyn std::io::Write,
+    value: &kv::Value<'_>,
+) -> std::io::Result<()> {
+    if let Some(s) = value.to_borrowed_str() {
+        write!(writer, "\"{}\"", escape_json_str(s))
+    } else if let Some(b) = value.to_bool() {
+        write!(writer, "{}", b)
+    } else if let Some(n) = value.to_i64() {
+        write!(writer, "{}", n)
+    } else if let Some(n) = value.to_u64() {
+        write!(writer, "{}", n)
+    } else if let Some(n) = value.to_f64() {
+        write!(writer, "{}", n)
+    } else {
+        write!(writer, "\"{}\"", escape_json_str(&value.to_string()))
+    }
+}
+
+/// Collects the structured key-value pairs attached to a [`Record`] and writes each one as a
+/// `,"key":value` fragment, stopping at the first I/O error.
+struct JsonFieldsVisitor<'a> {
+    writer: &'a mut dyn std::io::Write,
+    result: std::io::Result<()>,
+}
+
+impl<'kvs> kv::VisitSource<'kvs> for JsonFieldsVisitor<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        if key.as_str() == REPLAYED_TIMESTAMP_KEY {
+            // Consumed by `record_timestamp` above, not a real user field.
+            return Ok(());
+        }
+        if self.result.is_ok() {
+            self.result = write!(self.writer, ",\"{}\":", escape_json_str(key.as_str()))
+                .and_then(|()| write_json_kv_value(self.writer, &value));
+        }
+        Ok(())
+    }
 }
 
 /// Logs with color, contains the same information as the [pretty_env_logger].
@@ -102,7 +223,7 @@ pub fn color_logger_format(
     write!(
         writer,
         "{} {} {} > {}",
-        now.now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+        record_timestamp(now, record).format("%Y-%m-%dT%H:%M:%S%.3f"),
         style(level, level),
         record.module_path().unwrap_or("<unnamed>"),
         record.args(),
@@ -126,7 +247,7 @@ pub fn nocolor_logger_format(
     write!(
         writer,
         "{} {} {} > {}",
-        now.now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+        record_timestamp(now, record).format("%Y-%m-%dT%H:%M:%S%.3f"),
         record.level(),
         record.module_path().unwrap_or("<unnamed>"),
         record.args(),
@@ -157,12 +278,16 @@ pub fn nocolor_logger_format(
 ///
 /// Panics if a global logger was already set.
 pub fn init() {
-    flexi_logger::Logger::with_env()
-        .format(log_format())
-        .start()
+    init_with(LogConfig::default())
         .expect("Initializing logger failed. Was another logger already initialized?");
 }
 
+/// Like [`init`], but if a global logger was already set, this does nothing instead of
+/// panicking.
+pub fn maybe_init() {
+    let _ = init_with(LogConfig::default());
+}
+
 /// initializes a new logger that logs to an already opened [`std::fs::File`].
 ///
 /// If the environment variable `GOLOG_LOG_FMT=json` is set, then the output is formatted as JSON.
@@ -173,23 +298,160 @@ pub fn init() {
 ///
 /// [`std::fs::File`]: https://doc.rust-lang.org/std/fs/struct.File.html
 pub fn init_with_file(file: File) {
-    flexi_logger::Logger::with_env()
-        .log_target(LogTarget::Writer(Box::new(SingleFileWriter::new(file))))
-        .format(log_format())
-        .start()
-        .expect("Initializing logger failed. Was another logger already initialized?");
+    init_with(LogConfig {
+        target: LogDestination::File(file),
+        ..LogConfig::default()
+    })
+    .expect("Initializing logger failed. Was another logger already initialized?");
 }
 
-/// The log format is based on the `GOLOG_LOG_FMT` environment variable. It can be set to `json`.
-fn log_format() -> FormatFunction {
-    match env::var("GOLOG_LOG_FMT") {
-        Ok(ref format) if format == "json" => go_log_json_format,
-        _ => {
-            if atty::is(atty::Stream::Stderr) {
-                color_logger_format
-            } else {
-                nocolor_logger_format
-            }
+/// Initializes a new logger from a [`LogConfig`], instead of relying on the `RUST_LOG` and
+/// `GOLOG_LOG_FMT` environment variables.
+///
+/// Unlike [`init`] and [`init_with_file`], this returns the error instead of panicking, which
+/// makes it usable from libraries, tests and daemons that can't risk aborting the process just
+/// because a logger was already installed.
+///
+/// Returns the [`LoggerHandle`]. This matters in particular with [`LogConfig::async_writes`]
+/// set: the background thread it starts is only drained and joined when the writer holding it is
+/// dropped, which normal process exit does not do for you. Keep the handle and call
+/// [`LoggerHandle::shutdown`] during your own shutdown sequence if you need queued records
+/// flushed before the process exits; [`init`], [`maybe_init`] and [`init_with_file`] discard the
+/// handle and so can't offer that guarantee.
+///
+/// # Errors
+///
+/// Returns an error if a global logger was already set.
+pub fn init_with(config: LogConfig) -> Result<LoggerHandle, FlexiLoggerError> {
+    let format = config.format.resolve();
+    let async_writes = config.async_writes;
+
+    let mut logger = match config.filter {
+        Some(filter) => flexi_logger::Logger::with_str(filter),
+        None => flexi_logger::Logger::with_env(),
+    }
+    .format(format);
+
+    logger = match config.target {
+        LogDestination::Stderr => logger.log_target(LogTarget::StdErr),
+        LogDestination::Stdout => logger.log_target(LogTarget::StdOut),
+        LogDestination::File(file) => {
+            let mut writer: Box<dyn LogWriter> = Box::new(SingleFileWriter::new(file));
+            // Set the format here rather than relying on flexi_logger's own post-construction
+            // `.format()` call on the `LogTarget::Writer` box: once `maybe_async` wraps this in
+            // an `AsyncWriter`, that call would land on `AsyncWriter::format`, which is a no-op.
+            writer.format(format);
+            logger.log_target(LogTarget::Writer(maybe_async(writer, async_writes)))
+        }
+        LogDestination::RotatingFile {
+            dir,
+            basename,
+            rotate,
+            keep,
+        } => {
+            let mut writer: Box<dyn LogWriter> =
+                Box::new(RotatingFileWriter::new(dir, basename, rotate, keep)?);
+            writer.format(format);
+            logger.log_target(LogTarget::Writer(maybe_async(writer, async_writes)))
+        }
+        LogDestination::Syslog { facility, transport } => {
+            let writer = SyslogWriter::new(facility, transport)?;
+            logger.log_target(LogTarget::Writer(Box::new(writer)))
         }
+        LogDestination::Writer(writer) => {
+            // Go through `format_for_writer` instead of calling `writer.format()` directly:
+            // flexi_logger's own `.format()` call on the `LogTarget::Writer` box would otherwise
+            // overwrite the pipe formatter with `format`.
+            let logger = match config.pipe_formatter {
+                Some(pipe_formatter) => logger.format_for_writer(pipe_formatter),
+                None => logger,
+            };
+            logger.log_target(LogTarget::Writer(writer))
+        }
+    };
+
+    logger.start()
+}
+
+/// Initializes a new logger whose filter is read from `path` and live-reloaded whenever that
+/// file changes on disk, so an operator can bump verbosity without restarting the process.
+///
+/// `path` uses the same `target=level` directive syntax as `RUST_LOG`, e.g.
+/// `info,storage_proofs=debug` - a plain text file, not a flexi_logger TOML specfile (which
+/// [`flexi_logger::Logger::start_with_specfile`] requires and which uses a different,
+/// `global_level`/`[modules]` syntax). If `path` doesn't exist yet, the initial filter is
+/// `"info"`.
+///
+/// A background thread polls `path`'s modification time roughly once a second; whenever it
+/// changes, the file is re-read and re-parsed and the running logger's filter is updated via
+/// [`LoggerHandle::set_new_spec`]. A file that fails to parse is ignored and the previous filter
+/// stays in effect.
+///
+/// Returns the [`LoggerHandle`] so callers can also push spec changes programmatically, e.g.
+/// from an admin RPC endpoint.
+///
+/// # Errors
+///
+/// Returns an error if a global logger was already set, if `path` exists but can't be read, or
+/// if its initial contents don't parse as a filter directive.
+pub fn init_with_reloadable_spec(
+    path: impl AsRef<std::path::Path>,
+) -> Result<LoggerHandle, FlexiLoggerError> {
+    let path = path.as_ref().to_path_buf();
+    let initial = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => "info".to_string(),
+        Err(err) => return Err(err.into()),
+    };
+    let spec = flexi_logger::LogSpecification::parse(&initial)?;
+    let handle = flexi_logger::Logger::with(spec)
+        .format(LogFmt::default().resolve())
+        .start()?;
+
+    spawn_spec_reloader(path, handle.clone());
+    Ok(handle)
+}
+
+/// Polls `path` for changes roughly once a second and pushes any re-parseable update onto
+/// `handle`. Runs for the life of the process; there's no way to stop it short of exiting, since
+/// nothing else holds a reference to the thread once this function returns.
+fn spawn_spec_reloader(path: std::path::PathBuf, mut handle: LoggerHandle) {
+    let _ = thread::Builder::new()
+        .name("fil-logger-spec-reloader".to_string())
+        .spawn(move || {
+            let mut last_modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            loop {
+                thread::sleep(Duration::from_secs(1));
+
+                let modified = match std::fs::metadata(&path).and_then(|m| m.modified()) {
+                    Ok(modified) => modified,
+                    Err(_) => continue,
+                };
+                if Some(modified) == last_modified {
+                    continue;
+                }
+                last_modified = Some(modified);
+
+                if let Ok(contents) = std::fs::read_to_string(&path) {
+                    if let Ok(spec) = flexi_logger::LogSpecification::parse(&contents) {
+                        handle.set_new_spec(spec);
+                    }
+                }
+            }
+        });
+}
+
+/// Wraps `writer` in an [`AsyncWriter`] when `async_writes` is set, so [`LogDestination::File`]
+/// and [`LogDestination::RotatingFile`] can opt into off-thread writes through [`LogConfig`]
+/// without the caller having to construct an [`AsyncWriter`] themselves.
+fn maybe_async(writer: Box<dyn LogWriter>, async_writes: bool) -> Box<dyn LogWriter> {
+    if async_writes {
+        Box::new(AsyncWriter::new(
+            writer,
+            DEFAULT_ASYNC_CHANNEL_CAPACITY,
+            Overflow::Block,
+        ))
+    } else {
+        writer
     }
 }
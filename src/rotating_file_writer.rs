@@ -0,0 +1,308 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use flexi_logger::writers::LogWriter;
+use flexi_logger::{default_format, DeferredNow, FormatFunction};
+use log::Record;
+
+/// When a [`RotatingFileWriter`] rolls its current file over to a new one.
+pub enum Criterion {
+    /// Rotate once the current file has grown past this many bytes.
+    Size(u64),
+    /// Rotate when the local calendar date changes.
+    Age,
+}
+
+/// How a rolled-over file is named.
+pub enum Naming {
+    /// `basename.r00001.log`, `basename.r00002.log`, ... counting up from the oldest rotation.
+    Numbers,
+    /// `basename.<local-date>.log`, e.g. `basename.2024-06-17.log`.
+    Timestamps,
+}
+
+/// Pairs the rotation trigger with how the rolled-over file should be named.
+pub struct Rotate {
+    /// When to roll over.
+    pub criterion: Criterion,
+    /// How to name the rolled-over file.
+    pub naming: Naming,
+}
+
+/// What to do with files that have already been rotated out.
+pub enum Cleanup {
+    /// Keep only the newest `n` rotated files, deleting the rest.
+    KeepLogFiles(usize),
+    /// Gzip-compress rotated files once there are more than `n`, keeping the newest `n`
+    /// uncompressed.
+    KeepCompressedFiles(usize),
+}
+
+struct State {
+    file: File,
+    bytes_written: u64,
+    /// The local date (`%Y-%m-%d`) the current file was opened on, used by [`Criterion::Age`].
+    /// Empty until the first record is written, since opening the writer isn't itself a log
+    /// event.
+    opened_date: String,
+    /// Monotonic counter for [`Naming::Numbers`].
+    index: usize,
+}
+
+/// A [`LogWriter`] that rotates its output file by size or by calendar day, renames the rolled
+/// file according to a [`Naming`] scheme, and cleans up old rotations according to a [`Cleanup`]
+/// policy.
+///
+/// Unlike [`crate::SingleFileWriter`], which wraps a single file handle for the lifetime of the
+/// process, this manages the file itself so it can close, rename and reopen it on rotation.
+pub struct RotatingFileWriter {
+    dir: PathBuf,
+    basename: String,
+    rotate: Rotate,
+    keep: Cleanup,
+    state: Mutex<State>,
+    format: FormatFunction,
+}
+
+impl RotatingFileWriter {
+    /// Opens (or creates) `dir/basename`, rotating it according to `rotate` and cleaning up old
+    /// rotations according to `keep`.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        basename: impl Into<String>,
+        rotate: Rotate,
+        keep: Cleanup,
+    ) -> std::io::Result<Self> {
+        if matches!(rotate.criterion, Criterion::Size(_)) && matches!(rotate.naming, Naming::Timestamps)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Naming::Timestamps only disambiguates rotations by calendar day, so it can't be \
+                 paired with Criterion::Size: two size-triggered rotations on the same day would \
+                 rename to the same file and clobber each other. Use Naming::Numbers with \
+                 Criterion::Size, or Naming::Timestamps with Criterion::Age.",
+            ));
+        }
+
+        let dir = dir.into();
+        let basename = basename.into();
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::active_path(&dir, &basename))?;
+        let bytes_written = file.metadata()?.len();
+        // Seed from whatever rotations already exist on disk: a process restart recreates this
+        // writer with a fresh `State`, and starting `index` back at 0 would mean the next
+        // size-triggered rotation renames to `basename.r00001...` again, clobbering whatever
+        // rotation survived from the previous run.
+        let index = highest_numbered_index(&dir, &basename)?;
+        Ok(Self {
+            dir,
+            basename,
+            rotate,
+            keep,
+            state: Mutex::new(State {
+                file,
+                bytes_written,
+                opened_date: String::new(),
+                index,
+            }),
+            format: default_format,
+        })
+    }
+
+    fn active_path(dir: &Path, basename: &str) -> PathBuf {
+        dir.join(basename)
+    }
+
+    fn rotate_if_needed(&self, state: &mut State, now: &mut DeferredNow) -> std::io::Result<()> {
+        let today = now.now().format("%Y-%m-%d").to_string();
+        let should_rotate = match self.rotate.criterion {
+            Criterion::Size(max_bytes) => state.bytes_written > max_bytes,
+            Criterion::Age => !state.opened_date.is_empty() && state.opened_date != today,
+        };
+        if should_rotate {
+            self.rotate(state, &today)?;
+        }
+        if state.opened_date.is_empty() {
+            state.opened_date = today;
+        }
+        Ok(())
+    }
+
+    fn rotate(&self, state: &mut State, today: &str) -> std::io::Result<()> {
+        state.file.flush()?;
+        let active = Self::active_path(&self.dir, &self.basename);
+        let rotated_name = match self.rotate.naming {
+            Naming::Numbers => {
+                state.index += 1;
+                numbered_name(&self.basename, state.index)
+            }
+            Naming::Timestamps => timestamped_name(&self.basename, &state.opened_date),
+        };
+        fs::rename(&active, self.dir.join(rotated_name))?;
+        state.file = OpenOptions::new().create(true).append(true).open(&active)?;
+        state.bytes_written = 0;
+        state.opened_date = today.to_string();
+        self.cleanup()
+    }
+
+    fn cleanup(&self) -> std::io::Result<()> {
+        let mut rotated = self.rotated_files()?;
+        rotated.sort();
+        match self.keep {
+            Cleanup::KeepLogFiles(n) => {
+                for path in rotated.into_iter().rev().skip(n) {
+                    fs::remove_file(path)?;
+                }
+            }
+            Cleanup::KeepCompressedFiles(n) => {
+                for path in rotated.into_iter().rev().skip(n) {
+                    if path.extension().and_then(|ext| ext.to_str()) != Some("gz") {
+                        gzip_in_place(&path)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists already rotated files for this writer's basename, oldest and newest alike, in the
+    /// target directory. The still-active file is excluded.
+    fn rotated_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let active = Self::active_path(&self.dir, &self.basename);
+        let (stem, _ext) = split_basename(&self.basename);
+        let prefix = format!("{}.", stem);
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path == active {
+                continue;
+            }
+            if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix))
+            {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Scans `dir` for files already rotated from a previous run with [`Naming::Numbers`] and
+/// returns the highest index found, or `0` if there are none.
+fn highest_numbered_index(dir: &Path, basename: &str) -> std::io::Result<usize> {
+    let (stem, _ext) = split_basename(basename);
+    let prefix = format!("{}.r", stem);
+    let mut highest = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(digits) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let digits: String = digits.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if let Ok(index) = digits.parse::<usize>() {
+            highest = highest.max(index);
+        }
+    }
+    Ok(highest)
+}
+
+fn split_basename(basename: &str) -> (&str, &str) {
+    match basename.rsplit_once('.') {
+        Some((stem, ext)) => (stem, ext),
+        None => (basename, ""),
+    }
+}
+
+fn numbered_name(basename: &str, index: usize) -> String {
+    let (stem, ext) = split_basename(basename);
+    if ext.is_empty() {
+        format!("{}.r{:05}", stem, index)
+    } else {
+        format!("{}.r{:05}.{}", stem, index, ext)
+    }
+}
+
+fn timestamped_name(basename: &str, date: &str) -> String {
+    let (stem, ext) = split_basename(basename);
+    if ext.is_empty() {
+        format!("{}.{}", stem, date)
+    } else {
+        format!("{}.{}.{}", stem, date, ext)
+    }
+}
+
+/// Gzip-compresses `path` to `path` with a `.gz` suffix appended, then removes the original.
+fn gzip_in_place(path: &Path) -> std::io::Result<()> {
+    let mut input = File::open(path)?;
+    let mut gz_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+    gz_name.push_str(".gz");
+    let output = File::create(path.with_file_name(gz_name))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+/// Wraps a [`File`] and counts the bytes written through it, so [`RotatingFileWriter`] can check
+/// [`Criterion::Size`] without a separate `stat` call on every record.
+struct CountingWriter<'a> {
+    inner: &'a mut File,
+    written: &'a mut u64,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        *self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl LogWriter for RotatingFileWriter {
+    #[inline]
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+        let mut guard = self.state.lock().unwrap();
+        self.rotate_if_needed(&mut guard, now)?;
+        // Rebind through the guard first: borrowing `state.file` and `state.bytes_written`
+        // directly off `*guard` twice in the same expression is two overlapping mutable borrows
+        // of the same `MutexGuard` deref, which NLL rejects.
+        let state = &mut *guard;
+        let mut counting = CountingWriter {
+            inner: &mut state.file,
+            written: &mut state.bytes_written,
+        };
+        (self.format)(&mut counting, now, record)?;
+        writeln!(&mut counting)
+    }
+
+    #[inline]
+    fn flush(&self) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.file.flush()
+    }
+
+    fn format(&mut self, format: FormatFunction) {
+        self.format = format;
+    }
+
+    #[inline]
+    fn max_log_level(&self) -> log::LevelFilter {
+        log::LevelFilter::Trace
+    }
+}
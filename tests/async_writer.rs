@@ -0,0 +1,76 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use fil_logger::{go_log_json_format, AsyncWriter, Overflow};
+use flexi_logger::writers::LogWriter;
+use flexi_logger::{DeferredNow, FormatFunction};
+use log::Record;
+
+/// A `LogWriter` that sleeps before formatting each record, simulating a background thread
+/// that's busy draining a backlog rather than writing records as soon as they're logged.
+struct SlowWriter {
+    delay: Duration,
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl LogWriter for SlowWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        thread::sleep(self.delay);
+        let mut buf = self.buf.lock().unwrap();
+        go_log_json_format(&mut *buf, now, record)?;
+        writeln!(&mut buf)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn format(&mut self, _format: FormatFunction) {}
+
+    fn max_log_level(&self) -> log::LevelFilter {
+        log::LevelFilter::Trace
+    }
+}
+
+#[test]
+fn preserves_the_original_log_time_across_a_simulated_backlog() {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let inner: Box<dyn LogWriter> = Box::new(SlowWriter {
+        delay: Duration::from_millis(1500),
+        buf: Arc::clone(&buf),
+    });
+    let writer = AsyncWriter::new(inner, 8, Overflow::Block);
+
+    let logged_at = Local::now();
+    let args = format_args!("sent block");
+    let record = Record::builder()
+        .level(log::Level::Info)
+        .target("async_writer_test")
+        .args(args)
+        .build();
+    writer
+        .write(&mut DeferredNow::new(), &record)
+        .expect("write failed");
+    // Blocks until the background thread - which sleeps for `delay` before formatting - has
+    // drained this record, so the assertions below see its final output.
+    writer.flush().expect("flush failed");
+
+    let output = String::from_utf8(buf.lock().unwrap().clone()).expect("output must be valid UTF-8");
+    let ts = output
+        .split(r#""ts":""#)
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .expect("output should contain a ts field");
+    let rendered = DateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.3f%:z")
+        .expect("ts field should parse with the expected format");
+
+    let skew_ms = (rendered.timestamp_millis() - logged_at.timestamp_millis()).abs();
+    assert!(
+        skew_ms < 500,
+        "rendered timestamp should reflect when the record was logged, not when the backlogged \
+         background thread got around to it (skew: {skew_ms}ms): {output}"
+    );
+}
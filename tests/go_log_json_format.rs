@@ -0,0 +1,69 @@
+use fil_logger::go_log_json_format;
+use flexi_logger::DeferredNow;
+use log::{kv, Record};
+
+/// A fixed set of key-value pairs to attach to a [`Record`] for these tests, so we can check
+/// that `go_log_json_format` renders each value's JSON kind correctly.
+struct Fields<'a>(&'a [(&'a str, i64)]);
+
+impl<'a> kv::Source for Fields<'a> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn kv::VisitSource<'kvs>) -> Result<(), kv::Error> {
+        for (key, value) in self.0 {
+            visitor.visit_pair(kv::Key::from(*key), kv::Value::from(*value))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn escapes_quotes_backslashes_and_control_characters_in_the_message() {
+    let record = Record::builder()
+        .level(log::Level::Info)
+        .target("go_log_json_format")
+        .module_path(Some("go_log_json_format"))
+        .file(Some("tests/go_log_json_format.rs"))
+        .line(Some(1))
+        .args(format_args!(
+            "says \"hi\"\nwith a backslash \\ and a tab\t."
+        ))
+        .build();
+
+    let mut buf = Vec::new();
+    let mut now = DeferredNow::new();
+    go_log_json_format(&mut buf, &mut now, &record).expect("formatting failed");
+    let output = String::from_utf8(buf).expect("output must be valid UTF-8");
+
+    assert!(
+        output.contains(r#""msg":"says \"hi\"\nwith a backslash \\ and a tab\t.""#),
+        "message wasn't escaped into valid JSON: {output}"
+    );
+    assert!(
+        !output.contains('\n'),
+        "a raw newline would break line-delimited JSON output: {output}"
+    );
+}
+
+#[test]
+fn carries_structured_fields_with_their_original_json_kind() {
+    let fields = Fields(&[("bytes", 1024)]);
+    let record = Record::builder()
+        .level(log::Level::Info)
+        .target("go_log_json_format")
+        .args(format_args!("sent block"))
+        .key_values(&fields)
+        .build();
+
+    let mut buf = Vec::new();
+    let mut now = DeferredNow::new();
+    go_log_json_format(&mut buf, &mut now, &record).expect("formatting failed");
+    let output = String::from_utf8(buf).expect("output must be valid UTF-8");
+
+    assert!(
+        output.contains(r#""bytes":1024"#),
+        "an integer field should be rendered unquoted: {output}"
+    );
+    assert!(
+        !output.contains(r#""bytes":"1024""#),
+        "an integer field must not be rendered quoted: {output}"
+    );
+}
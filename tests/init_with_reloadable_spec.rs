@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+fn temp_spec_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fil-logger-test-{}-{name}.spec", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn starts_from_an_rust_log_style_directive_file_and_reloads_on_change() {
+    let path = temp_spec_path("reloadable-spec");
+    fs::write(&path, "info,storage_proofs=debug").expect("failed to write initial spec file");
+
+    let handle = fil_logger::init_with_reloadable_spec(&path)
+        .expect("init_with_reloadable_spec should accept a target=level directive file");
+    assert!(
+        log::log_enabled!(target: "storage_proofs", log::Level::Debug),
+        "the initial spec should have enabled debug logging for the storage_proofs target"
+    );
+    assert!(
+        !log::log_enabled!(target: "storage_proofs", log::Level::Trace),
+        "the initial spec shouldn't have enabled trace logging"
+    );
+
+    fs::write(&path, "info,storage_proofs=trace").expect("failed to rewrite spec file");
+    // The reloader polls roughly once a second; give it a few cycles to notice the change.
+    let mut reloaded = false;
+    for _ in 0..50 {
+        thread::sleep(Duration::from_millis(200));
+        if log::log_enabled!(target: "storage_proofs", log::Level::Trace) {
+            reloaded = true;
+            break;
+        }
+    }
+    assert!(
+        reloaded,
+        "the background reloader should have picked up the rewritten spec file"
+    );
+
+    drop(handle);
+    let _ = fs::remove_file(&path);
+}
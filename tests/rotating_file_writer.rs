@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::PathBuf;
+
+use fil_logger::{Cleanup, Criterion, Naming, Rotate, RotatingFileWriter};
+use flexi_logger::writers::LogWriter;
+use flexi_logger::DeferredNow;
+use log::Record;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fil-logger-test-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn write_record(writer: &RotatingFileWriter, message: &str) {
+    // `format_args!`'s result must be bound to a local: it borrows its own temporaries, which
+    // would otherwise be dropped at the end of this statement while `record` still borrows them.
+    let args = format_args!("{message}");
+    let record = Record::builder()
+        .level(log::Level::Info)
+        .target("rotating_file_writer_test")
+        .args(args)
+        .build();
+    let mut now = DeferredNow::new();
+    writer.write(&mut now, &record).expect("write failed");
+}
+
+#[test]
+fn rotates_by_size_and_cleans_up_old_files() {
+    let dir = temp_dir("size-rotation");
+    let writer = RotatingFileWriter::new(
+        dir.clone(),
+        "test.log",
+        Rotate {
+            criterion: Criterion::Size(10),
+            naming: Naming::Numbers,
+        },
+        Cleanup::KeepLogFiles(1),
+    )
+    .expect("failed to create writer");
+
+    for i in 0..5 {
+        write_record(&writer, &format!("line number {i} is long enough to rotate"));
+    }
+    writer.flush().expect("flush failed");
+
+    let mut rotated: Vec<_> = fs::read_dir(&dir)
+        .expect("failed to read temp dir")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "test.log")
+        .collect();
+    rotated.sort();
+
+    assert_eq!(
+        rotated.len(),
+        1,
+        "Cleanup::KeepLogFiles(1) should leave exactly one rotated file, found {rotated:?}"
+    );
+    assert!(
+        rotated[0].starts_with("test.r"),
+        "unexpected rotated file name: {}",
+        rotated[0]
+    );
+    assert!(
+        dir.join("test.log").exists(),
+        "the active file should still exist after rotating"
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rejects_size_criterion_paired_with_timestamp_naming() {
+    let dir = temp_dir("rejects-size-with-timestamps");
+    let result = RotatingFileWriter::new(
+        dir.clone(),
+        "test.log",
+        Rotate {
+            criterion: Criterion::Size(1024),
+            naming: Naming::Timestamps,
+        },
+        Cleanup::KeepLogFiles(1),
+    );
+
+    assert!(
+        result.is_err(),
+        "Criterion::Size with Naming::Timestamps should be rejected: \
+         same-day size rotations would clobber each other's file"
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}